@@ -1,11 +1,11 @@
 use std::io::{stdin, stdout, Write};
-use std::process::Command;
+use std::process::{Command, Stdio};
 use std::env;
 use std::path::Path;
 use std::collections::VecDeque;
 use crossterm::{
     cursor,
-    event::{self, Event, KeyCode, KeyEvent, KeyModifiers},
+    event::{self, DisableBracketedPaste, EnableBracketedPaste, Event, KeyCode, KeyEvent, KeyModifiers},
     execute,
     terminal::{self, ClearType},
     style::{Color, SetForegroundColor, ResetColor},
@@ -47,15 +47,50 @@ impl CommandHistory
         self.current_index = None;
     }
 
-    // Initial implementation just look at the start of all commands 
-    // TODO: find fuzzy search library
-    fn filter_commands(&mut self, start: &str)
-    {
-        self.filtered_commands = self.commands
+    // Loads persisted history from disk (newest entry last), respecting
+    // `max_size` and the same consecutive-duplicate dedup as `add`.
+    fn load(&mut self) {
+        let contents = match std::fs::read_to_string(history_file_path()) {
+            Ok(contents) => contents,
+            Err(_) => return,
+        };
+
+        for line in contents.lines() {
+            if !line.is_empty() {
+                self.add(line.to_string());
+            }
+        }
+    }
+
+    // Persists the full history to disk, oldest entry first.
+    fn save(&self) {
+        let contents = self.commands
             .iter()
-            .filter(|cmd| cmd.starts_with(start))
+            .rev()
             .cloned()
-            .collect();
+            .collect::<Vec<String>>()
+            .join("\n");
+
+        let _ = std::fs::write(history_file_path(), contents);
+    }
+
+    // Fuzzy subsequence match, ranked by score, ties broken by recency
+    fn filter_commands(&mut self, start: &str)
+    {
+        if start.is_empty() {
+            self.filtered_commands = self.commands.iter().cloned().collect();
+        } else {
+            let mut scored: Vec<(i32, usize, String)> = self.commands
+                .iter()
+                .enumerate()
+                .filter_map(|(idx, cmd)| fuzzy_score(start, cmd).map(|score| (score, idx, cmd.clone())))
+                .collect();
+
+            // Higher score first, then earlier (more recent) index first
+            scored.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+
+            self.filtered_commands = scored.into_iter().map(|(_, _, cmd)| cmd).collect();
+        }
 
         // Reset tab completion state when input changes
         self.tab_index = None;
@@ -126,50 +161,85 @@ impl CommandHistory
     }
 }
 
+// Score `candidate` against `query` as an ordered (not necessarily contiguous) subsequence match.
+// Returns None if some query char has no match left in the candidate.
+fn fuzzy_score(query: &str, candidate: &str) -> Option<i32> {
+    let query_chars: Vec<char> = query.chars().collect();
+    let cand_chars: Vec<char> = candidate.chars().collect();
+
+    let mut score = 0;
+    let mut q_idx = 0;
+    let mut prev_match_idx: Option<usize> = None;
+
+    for (i, &c) in cand_chars.iter().enumerate() {
+        if q_idx >= query_chars.len() {
+            break;
+        }
+
+        let qc = query_chars[q_idx];
+        if c.to_ascii_lowercase() != qc.to_ascii_lowercase() {
+            continue;
+        }
+
+        score += 1; // base point per matched char
+
+        if c == qc {
+            score += 1; // small bonus for matching case exactly
+        }
+
+        if prev_match_idx == Some(i.wrapping_sub(1)) && i > 0 {
+            score += 5; // consecutive-match bonus
+        }
+
+        let at_word_boundary = i == 0 || matches!(cand_chars[i - 1], ' ' | '/' | '-' | '_');
+        if at_word_boundary {
+            score += 3; // word-boundary bonus
+        }
+
+        prev_match_idx = Some(i);
+        q_idx += 1;
+    }
+
+    if q_idx == query_chars.len() {
+        Some(score)
+    } else {
+        None
+    }
+}
+
 /*
-    The idea is to simply have a list of recent commmands drop down while writing commands    
+    The idea is to simply have a list of recent commmands drop down while writing commands
  */
-fn display_suggestions(history: &CommandHistory, current_input: &str, cursor_pos: usize) 
+// Suggestions are drawn on the rows right below the (possibly wrapped) input
+// line, so they're positioned off `layout.rendered_rows` rather than with
+// moves relative to the cursor, which would land mid-input once it wraps.
+fn display_suggestions(history: &CommandHistory, layout: &LineLayout)
 {
     let suggestions = history.get_suggestions();
     if suggestions.is_empty() {
         return;
     }
 
-    if let Err(_) = execute!(stdout(), cursor::SavePosition) {
+    if let Err(_) = execute!(stdout(), cursor::Hide) {
         return;
     }
 
-    // Clear previous suggestions (up to 5 lines)
-    for i in 1..=5 {
-        if let Err(_) = execute!(
-            stdout(),
-            cursor::MoveDown(1),
-            cursor::MoveToColumn(0),
-            terminal::Clear(ClearType::CurrentLine),
-        ) {
-            return;
-        }
-    }
-
-    // Go back to first suggestion line
-    if let Err(_) = execute!(stdout(), cursor::RestorePosition, cursor::MoveDown(1)) {
-        return;
-    }
+    let start_row = layout.anchor_row + layout.rendered_rows;
 
     // Display up to 5 suggestions, with the current selection highlighted
-    for (i, suggestion) in suggestions.iter().take(5).enumerate() 
+    for (i, suggestion) in suggestions.iter().take(5).enumerate()
     {
         if let Err(_) = execute!(
             stdout(),
-            cursor::MoveToColumn(0)
+            cursor::MoveTo(0, start_row + i as u16),
+            terminal::Clear(ClearType::CurrentLine)
         ) {
             return;
         }
 
         // Highlight the current suggestion based on tab_index
         let is_selected = history.tab_index.map_or(false, |idx| i == idx);
-                
+
         if is_selected {
             if let Err(_) = execute!(stdout(), SetForegroundColor(Color::Green)) {
                 return;
@@ -185,68 +255,347 @@ fn display_suggestions(history: &CommandHistory, current_input: &str, cursor_pos
         if let Err(_) = execute!(stdout(), ResetColor) {
             return;
         }
+    }
 
-        // Move to next line for next suggestion
-        if i < suggestions.len() - 1 && i < 4 {
-            if let Err(_) = execute!(stdout(), cursor::MoveDown(1)) {
-                return;
+    // Restore cursor to its real position in the input line
+    if let Err(_) = execute!(stdout(), cursor::MoveTo(layout.cursor_col, layout.cursor_row), cursor::Show) {
+        return;
+    }
+    let _ = stdout().flush();
+}
+
+// Caches the executable names found on $PATH so every keystroke doesn't re-scan the filesystem.
+// Rescans automatically whenever the process's $PATH value differs from the cached one.
+struct PathCompletionCache {
+    path_var: String,
+    executables: Vec<String>,
+}
+
+impl PathCompletionCache {
+    fn new() -> Self {
+        PathCompletionCache {
+            path_var: String::new(),
+            executables: Vec::new(),
+        }
+    }
+
+    fn executables(&mut self) -> &[String] {
+        let current_path = env::var("PATH").unwrap_or_default();
+        if current_path != self.path_var {
+            self.executables = scan_path_executables(&current_path);
+            self.path_var = current_path;
+        }
+        &self.executables
+    }
+}
+
+#[cfg(unix)]
+fn is_executable_file(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    std::fs::metadata(path)
+        .map(|meta| meta.is_file() && meta.permissions().mode() & 0o111 != 0)
+        .unwrap_or(false)
+}
+
+fn scan_path_executables(path_var: &str) -> Vec<String> {
+    let mut names = std::collections::HashSet::new();
+
+    #[cfg(windows)]
+    let pathext: Vec<String> = env::var("PATHEXT")
+        .unwrap_or_else(|_| ".COM;.EXE;.BAT;.CMD".to_string())
+        .split(';')
+        .map(|ext| ext.to_lowercase())
+        .collect();
+
+    for dir in env::split_paths(path_var) {
+        let entries = match std::fs::read_dir(&dir) {
+            Ok(entries) => entries,
+            Err(_) => continue,
+        };
+
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let Some(name) = path.file_name().and_then(|n| n.to_str()) else {
+                continue;
+            };
+
+            #[cfg(unix)]
+            {
+                if is_executable_file(&path) {
+                    names.insert(name.to_string());
+                }
+            }
+
+            #[cfg(windows)]
+            {
+                let lower = name.to_lowercase();
+                if pathext.iter().any(|ext| lower.ends_with(ext.as_str())) {
+                    names.insert(name.to_string());
+                }
             }
         }
     }
 
-    // Reset color and restore cursor to original position
-    if let Err(_) = execute!(stdout(), ResetColor, cursor::RestorePosition) {
+    let mut names: Vec<String> = names.into_iter().collect();
+    names.sort();
+    names
+}
+
+// Completes a partial file/directory path by listing its parent directory.
+// `~` is expanded via `resolve_path` before hitting the filesystem, but the
+// completion is re-attached to the user's original (unexpanded) prefix.
+fn complete_file_path(partial: &str) -> Vec<String> {
+    // A bare `~` lists the home directory, keeping the `~/` prefix.
+    let partial = if partial == "~" { "~/" } else { partial };
+    let resolved = resolve_path(partial);
+    let file_prefix = match resolved.rfind('/') {
+        Some(idx) => &resolved[idx + 1..],
+        None => resolved.as_str(),
+    };
+    let search_dir = match resolved.rfind('/') {
+        Some(idx) if idx > 0 => &resolved[..idx],
+        Some(_) => "/",
+        None => ".",
+    };
+    let original_dir_part = match partial.rfind('/') {
+        Some(idx) => &partial[..=idx],
+        None => "",
+    };
+
+    let entries = match std::fs::read_dir(search_dir) {
+        Ok(entries) => entries,
+        Err(_) => return Vec::new(),
+    };
+
+    let mut matches: Vec<String> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            if !name.starts_with(file_prefix) {
+                return None;
+            }
+            let mut completed = format!("{}{}", original_dir_part, name);
+            if entry.path().is_dir() {
+                completed.push('/');
+            }
+            Some(completed)
+        })
+        .collect();
+
+    matches.sort();
+    matches
+}
+
+// Figures out which token the cursor sits in and completes it, producing full
+// candidate command lines (the rest of `input` is carried through unchanged)
+// so they can be cycled with the existing Tab/history suggestion machinery.
+fn completion_candidates(input: &str, cursor_pos: usize, path_cache: &mut PathCompletionCache) -> Vec<String> {
+    let before_cursor = &input[..cursor_pos];
+    let token_start = before_cursor
+        .rfind(char::is_whitespace)
+        .map_or(0, |idx| idx + 1);
+    let token = &input[token_start..cursor_pos];
+    let prefix = &input[..token_start];
+    let suffix = &input[cursor_pos..];
+    let is_first_token = prefix.trim().is_empty();
+
+    if token.is_empty() {
+        return Vec::new();
+    }
+
+    let names = if is_first_token {
+        path_cache
+            .executables()
+            .iter()
+            .filter(|name| name.starts_with(token))
+            .cloned()
+            .collect()
+    } else {
+        complete_file_path(token)
+    };
+
+    names
+        .into_iter()
+        .map(|name| format!("{}{}{}", prefix, name, suffix))
+        .collect()
+}
+
+fn clear_suggestions(layout: &LineLayout)
+{
+    let start_row = layout.anchor_row + layout.rendered_rows;
+
+    // Clear the next 5 lines (maximum number of suggestions)
+    for i in 0..5 {
+        if let Err(_) = execute!(
+            stdout(),
+            cursor::MoveTo(0, start_row + i),
+            terminal::Clear(ClearType::CurrentLine)
+        ) {
+            return;
+        }
+    }
+
+    if let Err(_) = execute!(stdout(), cursor::MoveTo(layout.cursor_col, layout.cursor_row)) {
         return;
     }
     let _ = stdout().flush();
 }
 
-fn clear_suggestions() 
+// Tracks where the prompt currently lives on screen, so redraws and the
+// suggestion overlay can account for input that wraps across more than one
+// physical terminal row.
+struct LineLayout {
+    anchor_row: u16,
+    rendered_rows: u16,
+    cursor_row: u16,
+    cursor_col: u16,
+}
+
+impl LineLayout {
+    fn new(anchor_row: u16) -> Self {
+        LineLayout {
+            anchor_row,
+            rendered_rows: 1,
+            cursor_row: anchor_row,
+            cursor_col: 0,
+        }
+    }
+}
+
+// Maps a logical offset (chars from the very start of the rendered prompt
+// line) to a physical (row, col), wrapping at `width` columns per row.
+fn logical_offset_to_physical(anchor_row: u16, offset: usize, width: u16) -> (u16, u16) {
+    let width = width.max(1) as usize;
+    let row = anchor_row + (offset / width) as u16;
+    let col = (offset % width) as u16;
+    (row, col)
+}
+
+fn redraw_line(hostname: &str, input: &str, cursor_pos: usize, layout: &mut LineLayout)
 {
-    if let Err(_) = execute!(stdout(), cursor::SavePosition) {
+    let (width, _height) = terminal::size().unwrap_or((80, 24));
+
+    let prompt_len = hostname.len() + 2;
+    let total_len = prompt_len + input.chars().count();
+    let current_rows = (((total_len.max(1) - 1) / width.max(1) as usize) + 1) as u16;
+
+    if let Err(_) = execute!(stdout(), cursor::Hide) {
         return;
     }
 
-    // Clear the next 5 lines (maximum number of suggestions)
-    for _ in 0..5 {
+    // Clear every row we (or the previous, possibly longer, render) occupied.
+    let rows_to_clear = layout.rendered_rows.max(current_rows);
+    for r in 0..rows_to_clear {
         if let Err(_) = execute!(
             stdout(),
-            cursor::MoveDown(1),
-            cursor::MoveToColumn(0),
+            cursor::MoveTo(0, layout.anchor_row + r),
             terminal::Clear(ClearType::CurrentLine)
         ) {
             return;
         }
     }
 
-    if let Err(_) = execute!(stdout(), cursor::RestorePosition) {
+    if let Err(_) = execute!(stdout(), cursor::MoveTo(0, layout.anchor_row)) {
+        return;
+    }
+    print!("{}> {}", hostname, input);
+    layout.rendered_rows = current_rows;
+
+    let (row, col) = logical_offset_to_physical(layout.anchor_row, prompt_len + cursor_pos, width);
+    layout.cursor_row = row;
+    layout.cursor_col = col;
+
+    if let Err(_) = execute!(stdout(), cursor::MoveTo(col, row), cursor::Show) {
         return;
     }
     let _ = stdout().flush();
 }
 
-fn redraw_line(hostname: &str, input: &str, cursor_pos: usize) 
-{
+// Dedicated redraw routine for reverse-i-search mode, distinct from `redraw_line`
+// since the prompt itself changes shape while searching.
+fn redraw_search_line(query: &str, matched: &str) {
     if let Err(_) = execute!(
         stdout(),
-        cursor::Hide, // remove flickering
+        cursor::Hide,
         cursor::MoveToColumn(0),
         terminal::Clear(ClearType::CurrentLine)
     ) {
         return;
     }
-    print!("{}> {}", hostname, input);
+    print!("(reverse-i-search)`{}': {}", query, matched);
 
-    if let Err(_) = execute!(
-        stdout(),
-        cursor::MoveToColumn((hostname.len() + 2 + cursor_pos) as u16),
-        cursor::Show
-    ) {
+    if let Err(_) = execute!(stdout(), cursor::Show) {
         return;
     }
     let _ = stdout().flush();
 }
 
+// Commands containing `query` as a substring, newest to oldest (the natural
+// order of `CommandHistory.commands`, since `add` pushes to the front).
+fn find_history_matches(history: &CommandHistory, query: &str) -> Vec<String> {
+    if query.is_empty() {
+        return Vec::new();
+    }
+    history.commands.iter().filter(|cmd| cmd.contains(query)).cloned().collect()
+}
+
+// Interactive Ctrl-R reverse incremental search. Runs its own key-handling
+// loop until the user accepts a match (Enter) or cancels (Esc/Ctrl-G); repeated
+// Ctrl-R stops at the oldest match. Returns the line to use for the prompt afterwards.
+fn reverse_search(history: &CommandHistory, original_input: &str) -> String {
+    let mut query = String::new();
+    let mut matches: Vec<String> = Vec::new();
+    let mut match_idx = 0;
+
+    redraw_search_line(&query, "");
+
+    loop {
+        let event = match event::read() {
+            Ok(event) => event,
+            Err(_) => continue,
+        };
+
+        match event {
+            Event::Key(KeyEvent { code, modifiers, .. }) => {
+                match code {
+                    KeyCode::Char('g') if modifiers.contains(KeyModifiers::CONTROL) => {
+                        return original_input.to_string();
+                    }
+                    KeyCode::Esc => {
+                        return original_input.to_string();
+                    }
+                    KeyCode::Enter => {
+                        return matches.get(match_idx).cloned().unwrap_or_else(|| original_input.to_string());
+                    }
+                    KeyCode::Char('r') if modifiers.contains(KeyModifiers::CONTROL) => {
+                        if !matches.is_empty() {
+                            match_idx = (match_idx + 1).min(matches.len() - 1);
+                        }
+                    }
+                    KeyCode::Backspace => {
+                        query.pop();
+                        matches = find_history_matches(history, &query);
+                        match_idx = 0;
+                    }
+                    KeyCode::Char(c) => {
+                        query.push(c);
+                        matches = find_history_matches(history, &query);
+                        match_idx = 0;
+                    }
+                    _ => {}
+                }
+
+                let matched = matches.get(match_idx).map(String::as_str).unwrap_or("");
+                redraw_search_line(&query, matched);
+            }
+            Event::Resize(_, _) => {
+                let matched = matches.get(match_idx).map(String::as_str).unwrap_or("");
+                redraw_search_line(&query, matched);
+            }
+            _ => {}
+        }
+    }
+}
 
 // if we cannot get to the home directory we fallback to the root directory
 fn get_home_directory() -> String {
@@ -280,7 +629,246 @@ fn resolve_path(path: &str) -> String
     path.to_string()
 }
 
-fn main() 
+fn history_file_path() -> String {
+    format!("{}/.simple_shell_history", get_home_directory().trim_end_matches('/'))
+}
+
+// A small ring buffer of killed (cut) text, readline/emacs style.
+struct KillRing {
+    entries: Vec<String>,
+    yank_index: Option<usize>,
+}
+
+impl KillRing {
+    const CAPACITY: usize = 16;
+
+    fn new() -> Self {
+        KillRing { entries: Vec::new(), yank_index: None }
+    }
+
+    fn kill(&mut self, text: String) {
+        if text.is_empty() {
+            return;
+        }
+        if self.entries.len() == Self::CAPACITY {
+            self.entries.remove(0);
+        }
+        self.entries.push(text);
+        self.yank_index = None;
+    }
+
+    // Most recent kill, or None if nothing has ever been killed.
+    fn yank(&mut self) -> Option<String> {
+        if self.entries.is_empty() {
+            return None;
+        }
+        self.yank_index = Some(self.entries.len() - 1);
+        self.entries.last().cloned()
+    }
+
+    // Next older kill after a yank, or None once the ring is exhausted.
+    fn yank_pop(&mut self) -> Option<String> {
+        let idx = self.yank_index?;
+        if idx == 0 {
+            return None;
+        }
+        self.yank_index = Some(idx - 1);
+        self.entries.get(idx - 1).cloned()
+    }
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric()
+}
+
+// Index of the start of the word immediately before `pos` (skipping any
+// separators first), treating runs of alphanumerics as words.
+fn word_start_before(input: &str, pos: usize) -> usize {
+    let chars: Vec<char> = input.chars().collect();
+    let mut i = pos.min(chars.len());
+    while i > 0 && !is_word_char(chars[i - 1]) {
+        i -= 1;
+    }
+    while i > 0 && is_word_char(chars[i - 1]) {
+        i -= 1;
+    }
+    i
+}
+
+// Index just past the end of the word at/after `pos` (skipping any
+// separators first).
+fn word_end_after(input: &str, pos: usize) -> usize {
+    let chars: Vec<char> = input.chars().collect();
+    let len = chars.len();
+    let mut i = pos.min(len);
+    while i < len && !is_word_char(chars[i]) {
+        i += 1;
+    }
+    while i < len && is_word_char(chars[i]) {
+        i += 1;
+    }
+    i
+}
+
+// Removes `input[start..end]` and stashes it in the kill ring.
+fn kill_range(input: &mut String, start: usize, end: usize, kill_ring: &mut KillRing) {
+    let killed: String = input.drain(start..end).collect();
+    kill_ring.kill(killed);
+}
+
+// A single stage of a pipeline, e.g. `grep rs > out.txt` in `cat file | grep rs > out.txt`.
+struct PipelineStage {
+    cmd: String,
+    args: Vec<String>,
+    stdin_file: Option<String>,
+    stdout_file: Option<(String, bool)>, // (path, append)
+}
+
+// Splits the trimmed input on `|` into stages, then pulls `<`, `>` and `>>`
+// redirection targets out of each stage's tokens.
+fn parse_pipeline(input: &str) -> Vec<PipelineStage> {
+    input.split('|').map(|stage| parse_stage(stage.trim())).collect()
+}
+
+fn parse_stage(stage: &str) -> PipelineStage {
+    let tokens: Vec<&str> = stage.split_whitespace().collect();
+
+    let mut args = Vec::new();
+    let mut stdin_file = None;
+    let mut stdout_file = None;
+
+    let mut i = 0;
+    while i < tokens.len() {
+        match tokens[i] {
+            "<" => {
+                stdin_file = tokens.get(i + 1).map(|target| target.to_string());
+                i += 2;
+            }
+            ">" => {
+                stdout_file = tokens.get(i + 1).map(|target| (target.to_string(), false));
+                i += 2;
+            }
+            ">>" => {
+                stdout_file = tokens.get(i + 1).map(|target| (target.to_string(), true));
+                i += 2;
+            }
+            token => {
+                args.push(token.to_string());
+                i += 1;
+            }
+        }
+    }
+
+    let cmd = if args.is_empty() { String::new() } else { args.remove(0) };
+
+    PipelineStage { cmd, args, stdin_file, stdout_file }
+}
+
+fn is_builtin(cmd: &str) -> bool {
+    matches!(cmd, "cd" | "pwd" | "exit" | "history")
+}
+
+fn run_builtin(stage: &PipelineStage, history: &CommandHistory) {
+    match stage.cmd.as_str() {
+        "cd" => {
+            // Default to the home directory if no argument is provided
+            let new_dir = stage.args.first().map_or_else(
+                || get_home_directory(),
+                |x| resolve_path(x),
+            );
+
+            let root = Path::new(&new_dir);
+            if let Err(e) = env::set_current_dir(&root) {
+                eprintln!("Failed to change directory to '{}': {}", new_dir, e);
+            }
+        }
+        "pwd" => {
+            match env::current_dir() {
+                Ok(path) => println!("{}", path.display()),
+                Err(e) => eprintln!("Failed to get current directory: {}", e),
+            }
+        }
+        "exit" => {
+            history.save();
+            let _ = execute!(stdout(), DisableBracketedPaste);
+            std::process::exit(0);
+        }
+        "history" => {
+            for (index, command) in history.commands.iter().enumerate() {
+                println!("{}\t{}", index + 1, command);
+            }
+        }
+        _ => unreachable!("run_builtin called with a non-builtin command"),
+    }
+}
+
+// Spawns every stage, wiring each one's stdout to the next stage's stdin via
+// `Stdio::piped()`, or to/from a file when the stage has a redirection.
+// Waits on the whole pipeline and returns the exit code of the last stage.
+fn run_pipeline(stages: Vec<PipelineStage>) -> i32 {
+    let last_idx = stages.len() - 1;
+    let mut children = Vec::new();
+    let mut previous_stdout: Option<std::process::ChildStdout> = None;
+
+    for (idx, stage) in stages.into_iter().enumerate() {
+        let stdin = if let Some(path) = &stage.stdin_file {
+            match std::fs::File::open(path) {
+                Ok(file) => Stdio::from(file),
+                Err(e) => {
+                    eprintln!("{}: {}", path, e);
+                    return 1;
+                }
+            }
+        } else if let Some(stdout) = previous_stdout.take() {
+            Stdio::from(stdout)
+        } else {
+            Stdio::inherit()
+        };
+
+        let stdout = if let Some((path, append)) = &stage.stdout_file {
+            let file = std::fs::OpenOptions::new()
+                .write(true)
+                .create(true)
+                .append(*append)
+                .truncate(!*append)
+                .open(path);
+
+            match file {
+                Ok(f) => Stdio::from(f),
+                Err(e) => {
+                    eprintln!("{}: {}", path, e);
+                    return 1;
+                }
+            }
+        } else if idx == last_idx {
+            Stdio::inherit()
+        } else {
+            Stdio::piped()
+        };
+
+        match Command::new(&stage.cmd).args(&stage.args).stdin(stdin).stdout(stdout).spawn() {
+            Ok(mut child) => {
+                previous_stdout = child.stdout.take();
+                children.push(child);
+            }
+            Err(e) => {
+                eprintln!("{}: {}", stage.cmd, e);
+                return 1;
+            }
+        }
+    }
+
+    let mut status_code = 0;
+    for mut child in children {
+        match child.wait() {
+            Ok(status) => status_code = status.code().unwrap_or(1),
+            Err(e) => eprintln!("Error waiting for child process: {}", e),
+        }
+    }
+    status_code
+}
+
+fn main()
 {
     let hostname = match env::var("HOSTNAME") {
         Ok(host) => host,
@@ -293,6 +881,9 @@ fn main()
     };
 
     let mut history = CommandHistory::new(64);
+    history.load();
+    let mut path_cache = PathCompletionCache::new();
+    let mut kill_ring = KillRing::new();
 
     // enable terminal raw mode so we can read incomplete commands
     // and do suggestions and cool stuff, but now we have to do 
@@ -301,30 +892,107 @@ fn main()
         eprintln!("Failed to enable raw mode");
         return;
     }
+    let _ = execute!(stdout(), EnableBracketedPaste);
 
-    loop 
+    loop
     {
         print!("{}> ", hostname);
         if let Err(_) = stdout().flush() {
             continue;
         }
 
+        // The row the prompt actually landed on (it may have scrolled from
+        // previous output), so wrapped redraws clear/redraw the right rows.
+        let anchor_row = cursor::position().map(|(_, row)| row).unwrap_or(0);
+        let mut layout = LineLayout::new(anchor_row);
+
         let mut input = String::new();
         let mut cursor_pos = 0;
+        // Range of the text last inserted by Ctrl-Y, so a following Alt-Y
+        // knows what to replace when rotating through older kills.
+        let mut last_yank_range: Option<(usize, usize)> = None;
 
         loop
         {
-            match event::read() 
+            match event::read()
             {
                 Ok(Event::Key(KeyEvent { code, modifiers, .. })) => {
 
+                    let is_yank_key = modifiers.contains(KeyModifiers::CONTROL) && code == KeyCode::Char('y')
+                        || modifiers.contains(KeyModifiers::ALT) && code == KeyCode::Char('y');
+                    if !is_yank_key {
+                        last_yank_range = None;
+                    }
+
                     if code == KeyCode::Char('c') && modifiers.contains(KeyModifiers::CONTROL) {
                         println!("^C");
+                        let _ = execute!(stdout(), DisableBracketedPaste);
                         std::process::exit(0);
-                    }
+                    } else if modifiers.contains(KeyModifiers::CONTROL) && code == KeyCode::Char('w')
+                        || modifiers.contains(KeyModifiers::ALT) && code == KeyCode::Backspace
+                    {
+                        let start = word_start_before(&input, cursor_pos);
+                        kill_range(&mut input, start, cursor_pos, &mut kill_ring);
+                        cursor_pos = start;
+                        history.filter_commands(&input);
+                        history.filtered_commands.extend(completion_candidates(&input, cursor_pos, &mut path_cache));
+                        redraw_line(&hostname, &input, cursor_pos, &mut layout);
+                    } else if modifiers.contains(KeyModifiers::CONTROL) && code == KeyCode::Char('u') {
+                        kill_range(&mut input, 0, cursor_pos, &mut kill_ring);
+                        cursor_pos = 0;
+                        history.filter_commands(&input);
+                        history.filtered_commands.extend(completion_candidates(&input, cursor_pos, &mut path_cache));
+                        redraw_line(&hostname, &input, cursor_pos, &mut layout);
+                    } else if modifiers.contains(KeyModifiers::CONTROL) && code == KeyCode::Char('k') {
+                        let end = input.len();
+                        kill_range(&mut input, cursor_pos, end, &mut kill_ring);
+                        history.filter_commands(&input);
+                        history.filtered_commands.extend(completion_candidates(&input, cursor_pos, &mut path_cache));
+                        redraw_line(&hostname, &input, cursor_pos, &mut layout);
+                    } else if modifiers.contains(KeyModifiers::CONTROL) && code == KeyCode::Char('a') {
+                        // Alias for Home
+                        cursor_pos = 0;
+                        redraw_line(&hostname, &input, cursor_pos, &mut layout);
+                    } else if modifiers.contains(KeyModifiers::CONTROL) && code == KeyCode::Char('e') {
+                        // Alias for End
+                        cursor_pos = input.len();
+                        redraw_line(&hostname, &input, cursor_pos, &mut layout);
+                    } else if modifiers.contains(KeyModifiers::ALT) && code == KeyCode::Char('b') {
+                        cursor_pos = word_start_before(&input, cursor_pos);
+                        redraw_line(&hostname, &input, cursor_pos, &mut layout);
+                    } else if modifiers.contains(KeyModifiers::ALT) && code == KeyCode::Char('f') {
+                        cursor_pos = word_end_after(&input, cursor_pos);
+                        redraw_line(&hostname, &input, cursor_pos, &mut layout);
+                    } else if modifiers.contains(KeyModifiers::CONTROL) && code == KeyCode::Char('y') {
+                        if let Some(text) = kill_ring.yank() {
+                            input.insert_str(cursor_pos, &text);
+                            last_yank_range = Some((cursor_pos, cursor_pos + text.len()));
+                            cursor_pos += text.len();
+                            history.filter_commands(&input);
+                            history.filtered_commands.extend(completion_candidates(&input, cursor_pos, &mut path_cache));
+                            redraw_line(&hostname, &input, cursor_pos, &mut layout);
+                        }
+                    } else if modifiers.contains(KeyModifiers::ALT) && code == KeyCode::Char('y') {
+                        if let Some((start, end)) = last_yank_range {
+                            if let Some(text) = kill_ring.yank_pop() {
+                                input.replace_range(start..end, &text);
+                                cursor_pos = start + text.len();
+                                last_yank_range = Some((start, cursor_pos));
+                                history.filter_commands(&input);
+                                history.filtered_commands.extend(completion_candidates(&input, cursor_pos, &mut path_cache));
+                                redraw_line(&hostname, &input, cursor_pos, &mut layout);
+                            }
+                        }
+                    } else if modifiers.contains(KeyModifiers::CONTROL) && code == KeyCode::Char('r') {
+                        input = reverse_search(&history, &input);
+                        cursor_pos = input.len();
+                        history.filter_commands(&input);
+                        history.filtered_commands.extend(completion_candidates(&input, cursor_pos, &mut path_cache));
+                        redraw_line(&hostname, &input, cursor_pos, &mut layout);
+                    } else {
                     match code {
                         KeyCode::Enter => {
-                            clear_suggestions(); // Clear suggestion lines before printing newline
+                            clear_suggestions(&layout); // Clear suggestion lines before printing newline
                             println!();
                             break;
                         }
@@ -333,71 +1001,51 @@ fn main()
                             input.insert(cursor_pos, c);
                             cursor_pos += 1;
                             history.filter_commands(&input);
-                            redraw_line(&hostname, &input, cursor_pos);
+                            history.filtered_commands.extend(completion_candidates(&input, cursor_pos, &mut path_cache));
+                            redraw_line(&hostname, &input, cursor_pos, &mut layout);
                         }
                         KeyCode::Backspace => {
                             if cursor_pos > 0 {
                                 cursor_pos -= 1;
                                 input.remove(cursor_pos);
                                 history.filter_commands(&input);
-                                redraw_line(&hostname, &input, cursor_pos);
+                            history.filtered_commands.extend(completion_candidates(&input, cursor_pos, &mut path_cache));
+                                redraw_line(&hostname, &input, cursor_pos, &mut layout);
                             }
                         }
                         KeyCode::Delete => {
                             if cursor_pos < input.len() {
                                 input.remove(cursor_pos);
                                 history.filter_commands(&input);
-                                redraw_line(&hostname, &input, cursor_pos);
+                            history.filtered_commands.extend(completion_candidates(&input, cursor_pos, &mut path_cache));
+                                redraw_line(&hostname, &input, cursor_pos, &mut layout);
                             }
                         }
                         KeyCode::Left => {
                             if cursor_pos > 0 {
                                 cursor_pos -= 1;
-                                redraw_line(&hostname, &input, cursor_pos);
+                                redraw_line(&hostname, &input, cursor_pos, &mut layout);
                             }
                         }
                         KeyCode::Right => {
                             if cursor_pos < input.len() {
                                 cursor_pos += 1;
-                                redraw_line(&hostname, &input, cursor_pos);
+                                redraw_line(&hostname, &input, cursor_pos, &mut layout);
                             }
                         }
                         KeyCode::Home => {
                             cursor_pos = 0;
-                            if let Err(_) = execute!(
-                                stdout(),
-                                cursor::MoveToColumn((hostname.len() + 2) as u16)
-                            ) {
-                                continue;
-                            }
+                            redraw_line(&hostname, &input, cursor_pos, &mut layout);
                         }
                         KeyCode::End => {
                             cursor_pos = input.len();
-                            if let Err(_) = execute!(
-                                stdout(),
-                                cursor::MoveToColumn((hostname.len() + 2 + cursor_pos) as u16)
-                            ) {
-                                continue;
-                            }
+                            redraw_line(&hostname, &input, cursor_pos, &mut layout);
                         }
                         KeyCode::Tab => {
                             if let Some(suggestion) = history.get_next_suggestion() {
-                                if let Err(_) = execute!(
-                                    stdout(),
-                                    cursor::Hide,
-                                    cursor::MoveToColumn(0),
-                                    terminal::Clear(ClearType::CurrentLine)
-                                ) {
-                                    continue;
-                                }
-                                
                                 input = suggestion;
-                                print!("{}> {}", hostname, input);
                                 cursor_pos = input.len();
-                                
-                                if let Err(_) = execute!(stdout(), cursor::Show) {
-                                    continue;
-                                }
+                                redraw_line(&hostname, &input, cursor_pos, &mut layout);
                             }
                         }
                         // For any other key press that modifies input, reset tab completion
@@ -407,85 +1055,65 @@ fn main()
                         }
                         _ => {}
                     }
-                    
+                    }
+
                     let _ = stdout().flush();
-                    display_suggestions(&history, &input, cursor_pos);
+                    display_suggestions(&history, &layout);
                 }
                 Ok(Event::Mouse(_)) => {}, // Ignore mouse events
                 Ok(Event::Resize(_, _)) => {
-                    redraw_line(&hostname, &input, cursor_pos);
-                }, // Handle terminal resize if needed
+                    // Terminal width changed, so the wrap layout needs recomputing.
+                    redraw_line(&hostname, &input, cursor_pos, &mut layout);
+                    display_suggestions(&history, &layout);
+                },
                 Ok(Event::FocusGained) => {}, // Ignore focus events
                 Ok(Event::FocusLost) => {}, // Ignore focus events
-                Ok(Event::Paste(_)) => {}, // Ignore paste events for now
+                Ok(Event::Paste(text)) => {
+                    input.insert_str(cursor_pos, &text);
+                    cursor_pos += text.chars().count();
+                    history.filter_commands(&input);
+                    history.filtered_commands.extend(completion_candidates(&input, cursor_pos, &mut path_cache));
+                    redraw_line(&hostname, &input, cursor_pos, &mut layout);
+                    display_suggestions(&history, &layout);
+                },
                 Err(_) => continue,
             }
         }
 
-        let mut input = input.trim().to_string();
+        let input = input.trim().to_string();
 
         if !input.is_empty() {
             history.add(input.clone());
+            history.save();
         }
-        history.add(input.to_string());
 
-    
-        let mut tokens = input.split_whitespace(); 
-        let cmd = match tokens.next() {
-            Some(c) => c,
-            None => continue, // Skip empty input
-        };
-        let args = tokens;
+        if input.is_empty() {
+            continue;
+        }
+
+        let stages = parse_pipeline(&input);
+        if stages.iter().any(|stage| stage.cmd.is_empty()) {
+            eprintln!("{}: syntax error near unexpected token", input.trim_end());
+            continue;
+        }
 
         /*
-            Some commands have to be built into the shell program 
+            Some commands have to be built into the shell program
             itself because they cannot work if they are external.
-            `cd` is one such since if it were external, it could only change 
-            its own directory; it couldn't affect the current working directory 
-            of the shell. 
+            `cd` is one such since if it were external, it could only change
+            its own directory; it couldn't affect the current working directory
+            of the shell.
          */
-        match cmd
-        {
-            "cd" => {
-                // Default to the home directory if no argument is provided
-                let new_dir = args.peekable().peek().map_or_else(
-                    || get_home_directory(),
-                    |x| resolve_path(x),
-                );
-
-                let root = Path::new(&new_dir);
-                if let Err(e) = env::set_current_dir(&root) {
-                    eprintln!("Failed to change directory to '{}': {}", new_dir, e);
-                }
-            },
-            "pwd" => {
-                match env::current_dir() {
-                    Ok(path) => println!("{}", path.display()),
-                    Err(e) => eprintln!("Failed to get current directory: {}", e),
-                }
-            },
-            "exit" => {
-                std::process::exit(0);
-            },
-            "history" => {
-                for (index, command) in history.commands.iter().enumerate() {
-                    println!("{}\t{}", index + 1, command);
-                }
-            },
-            cmd => {
-                let spawn_result = Command::new(cmd)
-                    .args(args)
-                    .spawn();
-        
-                match spawn_result {
-                    Ok(mut child) => {
-                        if let Err(e) = child.wait() {
-                            eprintln!("Error waiting for child process: {}", e);
-                        }
-                    },
-                    Err(e) => eprintln!("{}: {}",input.trim_end(), e)
-                }
-            }
+        if stages.len() == 1 && is_builtin(&stages[0].cmd) {
+            run_builtin(&stages[0], &history);
+            continue;
         }
+
+        if let Some(stage) = stages.iter().find(|stage| is_builtin(&stage.cmd)) {
+            eprintln!("{}: can only be used as a standalone command, not inside a pipeline", stage.cmd);
+            continue;
+        }
+
+        run_pipeline(stages);
     }
 }
\ No newline at end of file